@@ -0,0 +1,179 @@
+use crate::topology::{Direction, GraphIndex};
+use std::collections::HashMap;
+
+/// The immediate-dominator tree of the slice of a graph reachable from some root.
+/// Answers "which upstream node, if removed, disconnects this subtree" queries
+/// over a dependency graph.
+///
+/// IDs are plain node strings (already translated back through the interner),
+/// since this is a query-result type handed out to callers.
+pub struct DominatorTree {
+    root: String,
+    idom: HashMap<String, String>,
+}
+
+impl DominatorTree {
+    /// The immediate dominator of `node`, or `None` if `node` is the root or
+    /// wasn't reachable from it.
+    pub fn immediate_dominator(&self, node: &str) -> Option<&str> {
+        self.idom.get(node).map(|s| s.as_str())
+    }
+
+    /// The chain of dominators from `node`'s immediate dominator up to (and
+    /// including) the root. Empty if `node` is the root or unreachable.
+    pub fn dominators(&self, node: &str) -> Vec<String> {
+        let mut chain = Vec::new();
+        let mut current = match self.idom.get(node) {
+            Some(parent) => parent.clone(),
+            None => return chain,
+        };
+        loop {
+            let reached_root = current == self.root;
+            chain.push(current.clone());
+            if reached_root {
+                break;
+            }
+            current = match self.idom.get(&current) {
+                Some(parent) => parent.clone(),
+                None => break,
+            };
+        }
+        chain
+    }
+}
+
+/// Computes `DominatorTree` via the iterative Cooper-Harvey-Kennedy algorithm
+/// ("A Simple, Fast Dominance Algorithm"): DFS from `root` to assign postorder
+/// numbers, then iterate to a fixpoint in reverse postorder, intersecting each
+/// node's processed predecessors' idom chains.
+pub(crate) fn compute(
+    graph: &GraphIndex,
+    root: &str,
+    edge_type: Option<&str>,
+    direction: Direction,
+    as_of: Option<i64>,
+) -> DominatorTree {
+    let empty = DominatorTree {
+        root: root.to_string(),
+        idom: HashMap::new(),
+    };
+
+    let Some(root_id) = graph.lookup_id(root) else {
+        return empty;
+    };
+    if graph.is_node_deleted(root_id) {
+        return empty;
+    }
+    let type_id = edge_type.and_then(|t| graph.get_type_id(t));
+    if edge_type.is_some() && type_id.is_none() {
+        return empty;
+    }
+
+    let postorder = dfs_postorder(graph, root_id, type_id, direction, as_of);
+    let postorder_number: HashMap<u32, usize> = postorder
+        .iter()
+        .enumerate()
+        .map(|(i, &n)| (n, i))
+        .collect();
+
+    let mut idom: HashMap<u32, u32> = HashMap::new();
+    idom.insert(root_id, root_id);
+
+    // Reverse postorder: the root (highest postorder number) comes first.
+    let rpo: Vec<u32> = postorder.iter().rev().copied().collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &b in rpo.iter().skip(1) {
+            let preds: Vec<u32> = predecessors(graph, b, type_id, direction, as_of)
+                .into_iter()
+                .filter(|p| postorder_number.contains_key(p))
+                .collect();
+
+            let Some(&first_processed) = preds.iter().find(|p| idom.contains_key(p)) else {
+                continue;
+            };
+            let mut new_idom = first_processed;
+            for &p in &preds {
+                if p != new_idom && idom.contains_key(&p) {
+                    new_idom = intersect(new_idom, p, &idom, &postorder_number);
+                }
+            }
+
+            if idom.get(&b) != Some(&new_idom) {
+                idom.insert(b, new_idom);
+                changed = true;
+            }
+        }
+    }
+
+    let idom = idom
+        .into_iter()
+        .filter(|&(b, _)| b != root_id)
+        .filter_map(|(b, p)| Some((graph.lookup_str(b)?.to_string(), graph.lookup_str(p)?.to_string())))
+        .collect();
+
+    DominatorTree {
+        root: root.to_string(),
+        idom,
+    }
+}
+
+/// Walks two idom-chain fingers up toward the root, always advancing whichever
+/// currently sits at the smaller postorder number, until they meet at the
+/// nodes' common dominator.
+fn intersect(mut a: u32, mut b: u32, idom: &HashMap<u32, u32>, postorder_number: &HashMap<u32, usize>) -> u32 {
+    while a != b {
+        while postorder_number[&a] < postorder_number[&b] {
+            a = idom[&a];
+        }
+        while postorder_number[&b] < postorder_number[&a] {
+            b = idom[&b];
+        }
+    }
+    a
+}
+
+fn predecessors(
+    graph: &GraphIndex,
+    node: u32,
+    type_id: Option<u8>,
+    direction: Direction,
+    as_of: Option<i64>,
+) -> Vec<u32> {
+    graph.neighbors_any_type(node, type_id, direction.opposite(), as_of)
+}
+
+/// Iterative (explicit-stack) postorder DFS from `root`, skipping deleted nodes.
+fn dfs_postorder(
+    graph: &GraphIndex,
+    root: u32,
+    type_id: Option<u8>,
+    direction: Direction,
+    as_of: Option<i64>,
+) -> Vec<u32> {
+    let mut visited = std::collections::HashSet::new();
+    let mut postorder = Vec::new();
+    let mut stack: Vec<(u32, std::vec::IntoIter<u32>)> = Vec::new();
+
+    visited.insert(root);
+    stack.push((root, graph.neighbors_any_type(root, type_id, direction, as_of).into_iter()));
+
+    while let Some((node, children)) = stack.last_mut() {
+        match children.next() {
+            Some(next) => {
+                if !graph.is_node_deleted(next) && visited.insert(next) {
+                    let grandchildren = graph.neighbors_any_type(next, type_id, direction, as_of);
+                    stack.push((next, grandchildren.into_iter()));
+                }
+            }
+            None => {
+                postorder.push(*node);
+                stack.pop();
+            }
+        }
+    }
+
+    postorder
+}
@@ -1,6 +1,8 @@
 pub mod interner;
 pub mod topology;
 pub mod matcher;
+pub mod dominator;
 
 pub use interner::Interner;
-pub use topology::{GraphIndex, Direction};
\ No newline at end of file
+pub use topology::{GraphIndex, Direction, SnapshotError};
+pub use dominator::DominatorTree;
\ No newline at end of file
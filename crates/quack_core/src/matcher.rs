@@ -15,11 +15,15 @@ pub struct PatternEdge {
 /// Assumptions:
 /// 1. Variable 0 is the "start" variable, seeded by `start_candidates`.
 /// 2. The pattern is connected: for any variable `i > 0`, there is at least one constraint
-///    connecting it to a variable `j < i`.
+///    connecting it to some other variable.
 pub struct Matcher<'a> {
     graph: &'a GraphIndex,
     pattern: &'a [PatternEdge],
     num_vars: usize,
+    /// Search order: `order[step]` is the pattern variable assigned at that step.
+    /// Built by `Self::build_order` using VF2-style connectivity heuristics so that
+    /// each step is maximally constrained by what's already assigned.
+    order: Vec<usize>,
 }
 
 impl<'a> Matcher<'a> {
@@ -28,14 +32,77 @@ impl<'a> Matcher<'a> {
         for e in pattern {
             max_var = max_var.max(e.src_var).max(e.tgt_var);
         }
+        let num_vars = max_var + 1;
+        let order = Self::build_order(num_vars, pattern);
         Self {
             graph,
             pattern,
-            num_vars: max_var + 1,
+            num_vars,
+            order,
         }
     }
 
-    pub fn find_matches(&self, start_candidates: &[u32]) -> Vec<Vec<u32>> {
+    /// Orders variables starting from 0, then greedily picks the unassigned variable
+    /// with the most edges back into the already-ordered set (ties broken by total
+    /// incident edge count). This maximizes constraint propagation early, so candidate
+    /// sets shrink fast instead of enumerating whatever pattern edge comes first.
+    ///
+    /// If a variable has no connection to the ordered prefix (a disconnected pattern
+    /// component), that component is started at the lowest-numbered unassigned variable
+    /// and the greedy ordering continues from there.
+    fn build_order(num_vars: usize, pattern: &[PatternEdge]) -> Vec<usize> {
+        let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); num_vars];
+        for e in pattern {
+            if e.src_var != e.tgt_var {
+                adjacency[e.src_var].push(e.tgt_var);
+                adjacency[e.tgt_var].push(e.src_var);
+            }
+        }
+
+        let mut ordered = vec![false; num_vars];
+        let mut order = Vec::with_capacity(num_vars);
+        ordered[0] = true;
+        order.push(0);
+
+        while order.len() < num_vars {
+            let mut best: Option<(usize, usize, usize)> = None; // (var, edges_to_ordered, total_incident)
+            for v in 0..num_vars {
+                if ordered[v] {
+                    continue;
+                }
+                let edges_to_ordered = adjacency[v].iter().filter(|&&u| ordered[u]).count();
+                if edges_to_ordered == 0 {
+                    continue;
+                }
+                let total_incident = adjacency[v].len();
+                let is_better = match best {
+                    None => true,
+                    Some((_, be, bt)) => (edges_to_ordered, total_incident) > (be, bt),
+                };
+                if is_better {
+                    best = Some((v, edges_to_ordered, total_incident));
+                }
+            }
+
+            match best {
+                Some((v, ..)) => {
+                    ordered[v] = true;
+                    order.push(v);
+                }
+                None => {
+                    // No unassigned variable connects to the ordered set: start a new
+                    // component at the lowest-numbered unassigned variable.
+                    let next = (0..num_vars).find(|&v| !ordered[v]).unwrap();
+                    ordered[next] = true;
+                    order.push(next);
+                }
+            }
+        }
+
+        order
+    }
+
+    pub fn find_matches(&self, start_candidates: &[u32], as_of: Option<i64>) -> Vec<Vec<u32>> {
         let mut results = Vec::new();
         let mut assignment = vec![None; self.num_vars];
         let mut used_nodes = HashSet::new();
@@ -47,9 +114,9 @@ impl<'a> Matcher<'a> {
 
             assignment[0] = Some(start_node);
             used_nodes.insert(start_node);
-            
-            self.backtrack(1, &mut assignment, &mut used_nodes, &mut results);
-            
+
+            self.backtrack(1, &mut assignment, &mut used_nodes, &mut results, as_of);
+
             used_nodes.remove(&start_node);
             assignment[0] = None;
         }
@@ -59,41 +126,54 @@ impl<'a> Matcher<'a> {
 
     fn backtrack(
         &self,
-        current_var: usize,
+        step: usize,
         assignment: &mut Vec<Option<u32>>,
         used_nodes: &mut HashSet<u32>,
         results: &mut Vec<Vec<u32>>,
+        as_of: Option<i64>,
     ) {
-        if current_var == self.num_vars {
+        if step == self.num_vars {
             results.push(assignment.iter().map(|opt| opt.unwrap()).collect());
             return;
         }
 
-        let mut candidates: Option<Vec<u32>> = None;
+        let current_var = self.order[step];
 
+        // Gather a neighbor list per constraint connecting `current_var` to an
+        // already-assigned variable, then intersect starting from the smallest list
+        // so the working set shrinks as fast as possible.
+        let mut neighbor_lists: Vec<Vec<u32>> = Vec::new();
         for edge in self.pattern {
-            if edge.src_var < current_var && edge.tgt_var == current_var {
-                let known_node = assignment[edge.src_var].unwrap();
-                let neighbors = self.graph.get_neighbors(known_node, edge.type_id, Direction::Outgoing);
-                candidates = self.intersect(candidates, neighbors);
-                if candidates.as_ref().is_some_and(|c| c.is_empty()) { return; }
-            }
-            else if edge.src_var == current_var && edge.tgt_var < current_var {
-                let known_node = assignment[edge.tgt_var].unwrap();
-                let neighbors = self.graph.get_neighbors(known_node, edge.type_id, Direction::Incoming);
-                candidates = self.intersect(candidates, neighbors);
-                if candidates.as_ref().is_some_and(|c| c.is_empty()) { return; }
+            if edge.tgt_var == current_var && edge.src_var != current_var {
+                if let Some(known_node) = assignment[edge.src_var] {
+                    neighbor_lists.push(self.graph.get_neighbors(known_node, edge.type_id, Direction::Outgoing, as_of));
+                }
+            } else if edge.src_var == current_var && edge.tgt_var != current_var {
+                if let Some(known_node) = assignment[edge.tgt_var] {
+                    neighbor_lists.push(self.graph.get_neighbors(known_node, edge.type_id, Direction::Incoming, as_of));
+                }
             }
         }
-        
+
+        if neighbor_lists.is_empty() {
+            return;
+        }
+        neighbor_lists.sort_by_key(|l| l.len());
+
+        let mut candidates: Option<Vec<u32>> = None;
+        for list in neighbor_lists {
+            candidates = self.intersect(candidates, list);
+            if candidates.as_ref().is_some_and(|c| c.is_empty()) { return; }
+        }
+
         if let Some(cands) = candidates {
             for cand in cands {
                 if !used_nodes.contains(&cand) {
                     assignment[current_var] = Some(cand);
                     used_nodes.insert(cand);
-                    
-                    self.backtrack(current_var + 1, assignment, used_nodes, results);
-                    
+
+                    self.backtrack(step + 1, assignment, used_nodes, results, as_of);
+
                     used_nodes.remove(&cand);
                     assignment[current_var] = None;
                 }
@@ -101,13 +181,20 @@ impl<'a> Matcher<'a> {
         }
     }
 
+    /// Intersects `current` with `next`, building the `HashSet` from whichever side
+    /// is shorter so large candidate sets don't pay to hash themselves unnecessarily.
     fn intersect(&self, current: Option<Vec<u32>>, next: Vec<u32>) -> Option<Vec<u32>> {
         match current {
             None => Some(next),
             Some(curr) => {
-                let set: HashSet<_> = next.into_iter().collect();
-                Some(curr.into_iter().filter(|id| set.contains(id)).collect())
+                if curr.len() <= next.len() {
+                    let set: HashSet<_> = curr.into_iter().collect();
+                    Some(next.into_iter().filter(|id| set.contains(id)).collect())
+                } else {
+                    let set: HashSet<_> = next.into_iter().collect();
+                    Some(curr.into_iter().filter(|id| set.contains(id)).collect())
+                }
             }
         }
     }
-}
\ No newline at end of file
+}
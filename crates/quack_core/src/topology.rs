@@ -0,0 +1,644 @@
+use crate::interner::Interner;
+use serde::{Deserialize, Serialize};
+
+/// Direction of traversal relative to a node: outgoing edges leave the node,
+/// incoming edges arrive at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Outgoing,
+    Incoming,
+}
+
+impl Direction {
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Outgoing => Direction::Incoming,
+            Direction::Incoming => Direction::Outgoing,
+        }
+    }
+}
+
+/// One side of an adjacency entry: the node on the other end of the edge,
+/// plus the edge's type and temporal validity window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredEdge {
+    other: u32,
+    type_id: u8,
+    valid_from: Option<i64>,
+    valid_to: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct NodeData {
+    deleted: bool,
+    outgoing: Vec<StoredEdge>,
+    incoming: Vec<StoredEdge>,
+}
+
+/// The core graph topology: interned nodes with typed, temporally-scoped
+/// directed edges. Node IDs are strings (DuckDB UUIDs/keys); internally
+/// everything is addressed by the `u32` indices handed out by `Interner`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphIndex {
+    interner: Interner,
+    edge_types: Interner,
+    nodes: Vec<NodeData>,
+}
+
+impl GraphIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interns `id`, creating its adjacency slot if this is the first time
+    /// the node has been seen. Un-deletes the node if it was previously removed.
+    pub fn get_or_create_node(&mut self, id: &str) -> u32 {
+        let nid = self.interner.intern(id);
+        if self.nodes.len() <= nid as usize {
+            self.nodes.resize_with(nid as usize + 1, NodeData::default);
+        }
+        self.nodes[nid as usize].deleted = false;
+        nid
+    }
+
+    pub fn is_node_deleted(&self, node: u32) -> bool {
+        self.nodes
+            .get(node as usize)
+            .map(|n| n.deleted)
+            .unwrap_or(true)
+    }
+
+    /// Soft-deletes a node. Its adjacency is left in place (so in-flight
+    /// traversals aren't invalidated) and its edges still count toward
+    /// `edge_count`. Traversals, topological order and dominator queries skip
+    /// it; pattern matching skips it only as a start candidate.
+    pub fn remove_node(&mut self, id: &str) {
+        if let Some(nid) = self.interner.lookup_id(id) {
+            if let Some(node) = self.nodes.get_mut(nid as usize) {
+                node.deleted = true;
+            }
+        }
+    }
+
+    pub fn add_edge(
+        &mut self,
+        source: &str,
+        target: &str,
+        edge_type: &str,
+        valid_from: Option<i64>,
+        valid_to: Option<i64>,
+    ) {
+        let src = self.get_or_create_node(source);
+        let tgt = self.get_or_create_node(target);
+        let type_id = self.intern_edge_type(edge_type);
+
+        self.nodes[src as usize].outgoing.push(StoredEdge {
+            other: tgt,
+            type_id,
+            valid_from,
+            valid_to,
+        });
+        self.nodes[tgt as usize].incoming.push(StoredEdge {
+            other: src,
+            type_id,
+            valid_from,
+            valid_to,
+        });
+    }
+
+    pub fn remove_edge(&mut self, source: &str, target: &str, edge_type: &str) {
+        let (Some(src), Some(tgt), Some(type_id)) = (
+            self.interner.lookup_id(source),
+            self.interner.lookup_id(target),
+            self.get_type_id(edge_type),
+        ) else {
+            return;
+        };
+        self.nodes[src as usize]
+            .outgoing
+            .retain(|e| !(e.other == tgt && e.type_id == type_id));
+        self.nodes[tgt as usize]
+            .incoming
+            .retain(|e| !(e.other == src && e.type_id == type_id));
+    }
+
+    /// Ingests one Arrow `RecordBatch` of edges, expecting `source`, `target`
+    /// and `edge_type` string columns plus optional `valid_from`/`valid_to`
+    /// int64 (micros) columns. Does not deduplicate; call `compact` after the
+    /// last batch.
+    pub fn add_arrow_batch(&mut self, batch: &arrow::record_batch::RecordBatch) -> Result<(), String> {
+        use arrow::array::{Array, Int64Array, StringArray};
+
+        let col = |name: &str| {
+            batch
+                .column_by_name(name)
+                .ok_or_else(|| format!("arrow batch missing `{name}` column"))
+        };
+        let as_strings = |name: &str| -> Result<&StringArray, String> {
+            col(name)?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| format!("`{name}` column is not a utf8 string array"))
+        };
+        let as_i64 = |name: &str| -> Result<Option<&Int64Array>, String> {
+            match batch.column_by_name(name) {
+                None => Ok(None),
+                Some(c) => c
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .map(Some)
+                    .ok_or_else(|| format!("`{name}` column is not an int64 array")),
+            }
+        };
+
+        let sources = as_strings("source")?;
+        let targets = as_strings("target")?;
+        let edge_types = as_strings("edge_type")?;
+        let valid_froms = as_i64("valid_from")?;
+        let valid_tos = as_i64("valid_to")?;
+
+        for row in 0..batch.num_rows() {
+            let valid_from = valid_froms.filter(|a| a.is_valid(row)).map(|a| a.value(row));
+            let valid_to = valid_tos.filter(|a| a.is_valid(row)).map(|a| a.value(row));
+            self.add_edge(
+                sources.value(row),
+                targets.value(row),
+                edge_types.value(row),
+                valid_from,
+                valid_to,
+            );
+        }
+        Ok(())
+    }
+
+    /// Deduplicates adjacency entries (bulk ingestion may add the same edge
+    /// more than once) and shrinks backing storage to fit.
+    pub fn compact(&mut self) {
+        for node in &mut self.nodes {
+            dedup_edges(&mut node.outgoing);
+            dedup_edges(&mut node.incoming);
+            node.outgoing.shrink_to_fit();
+            node.incoming.shrink_to_fit();
+        }
+        self.nodes.shrink_to_fit();
+    }
+
+    pub fn get_type_id(&self, edge_type: &str) -> Option<u8> {
+        self.edge_types.lookup_id(edge_type).map(|id| id as u8)
+    }
+
+    fn intern_edge_type(&mut self, edge_type: &str) -> u8 {
+        self.edge_types.intern(edge_type) as u8
+    }
+
+    pub fn lookup_id(&self, id: &str) -> Option<u32> {
+        self.interner.lookup_id(id)
+    }
+
+    pub fn lookup_str(&self, id: u32) -> Option<&str> {
+        self.interner.lookup(id)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.iter().filter(|n| !n.deleted).count()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.nodes.iter().map(|n| n.outgoing.len()).sum()
+    }
+
+    /// Returns the ids of nodes reachable in one hop of type `type_id` from
+    /// `node`, following `direction`, restricted to edges valid at `as_of`
+    /// (or all edges, if `as_of` is `None`).
+    pub fn get_neighbors(
+        &self,
+        node: u32,
+        type_id: u8,
+        direction: Direction,
+        as_of: Option<i64>,
+    ) -> Vec<u32> {
+        let Some(data) = self.nodes.get(node as usize) else {
+            return Vec::new();
+        };
+        let edges = match direction {
+            Direction::Outgoing => &data.outgoing,
+            Direction::Incoming => &data.incoming,
+        };
+        edges
+            .iter()
+            .filter(|e| e.type_id == type_id && is_active_at(e.valid_from, e.valid_to, as_of))
+            .map(|e| e.other)
+            .collect()
+    }
+
+    pub(crate) fn neighbors_any_type(
+        &self,
+        node: u32,
+        edge_type: Option<u8>,
+        direction: Direction,
+        as_of: Option<i64>,
+    ) -> Vec<u32> {
+        let Some(data) = self.nodes.get(node as usize) else {
+            return Vec::new();
+        };
+        let edges = match direction {
+            Direction::Outgoing => &data.outgoing,
+            Direction::Incoming => &data.incoming,
+        };
+        edges
+            .iter()
+            .filter(|e| edge_type.is_none_or(|t| e.type_id == t))
+            .filter(|e| is_active_at(e.valid_from, e.valid_to, as_of))
+            .map(|e| e.other)
+            .collect()
+    }
+
+    /// Single-hop traversal from `sources`, returning unique reachable node IDs.
+    pub fn traverse(
+        &self,
+        sources: &[String],
+        edge_type: Option<&str>,
+        direction: Direction,
+        as_of: Option<i64>,
+    ) -> Vec<String> {
+        let type_id = edge_type.and_then(|t| self.get_type_id(t));
+        if edge_type.is_some() && type_id.is_none() {
+            return Vec::new();
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for source in sources {
+            let Some(nid) = self.interner.lookup_id(source) else {
+                continue;
+            };
+            if self.is_node_deleted(nid) {
+                continue;
+            }
+            for n in self.neighbors_any_type(nid, type_id, direction, as_of) {
+                if !self.is_node_deleted(n) {
+                    seen.insert(n);
+                }
+            }
+        }
+
+        seen.into_iter()
+            .filter_map(|id| self.lookup_str(id).map(|s| s.to_string()))
+            .collect()
+    }
+
+    /// BFS from `sources`, returning unique node IDs reachable within
+    /// `[min_depth, max_depth]` hops.
+    pub fn traverse_recursive(
+        &self,
+        sources: &[String],
+        edge_type: Option<&str>,
+        direction: Direction,
+        min_depth: usize,
+        max_depth: usize,
+        as_of: Option<i64>,
+    ) -> Vec<String> {
+        let type_id = edge_type.and_then(|t| self.get_type_id(t));
+        if edge_type.is_some() && type_id.is_none() {
+            return Vec::new();
+        }
+
+        let mut visited = std::collections::HashMap::new();
+        let mut frontier: Vec<u32> = Vec::new();
+        for source in sources {
+            if let Some(nid) = self.interner.lookup_id(source) {
+                if !self.is_node_deleted(nid) && !visited.contains_key(&nid) {
+                    visited.insert(nid, 0usize);
+                    frontier.push(nid);
+                }
+            }
+        }
+
+        let mut depth = 0usize;
+        while !frontier.is_empty() && depth < max_depth {
+            depth += 1;
+            let mut next = Vec::new();
+            for node in frontier {
+                for n in self.neighbors_any_type(node, type_id, direction, as_of) {
+                    if self.is_node_deleted(n) {
+                        continue;
+                    }
+                    if !visited.contains_key(&n) {
+                        visited.insert(n, depth);
+                        next.push(n);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        visited
+            .into_iter()
+            .filter(|&(_, d)| d >= min_depth && d <= max_depth)
+            .filter_map(|(id, _)| self.lookup_str(id).map(|s| s.to_string()))
+            .collect()
+    }
+
+    /// Computes a topological order of the non-deleted nodes over the slice of
+    /// `edge_type` edges valid at `as_of`, following outgoing edges. Returns
+    /// `Err` with the node IDs forming a detected cycle (in cycle order) if the
+    /// slice isn't a DAG.
+    pub fn topological_order(
+        &self,
+        edge_type: Option<&str>,
+        as_of: Option<i64>,
+    ) -> Result<Vec<String>, Vec<String>> {
+        const WHITE: u8 = 0;
+        const GRAY: u8 = 1;
+        const BLACK: u8 = 2;
+
+        let type_id = edge_type.and_then(|t| self.get_type_id(t));
+        let type_missing = edge_type.is_some() && type_id.is_none();
+
+        let mut color = vec![WHITE; self.nodes.len()];
+        let mut finished = Vec::with_capacity(self.nodes.len());
+
+        for start in 0..self.nodes.len() as u32 {
+            if self.is_node_deleted(start) || color[start as usize] != WHITE {
+                continue;
+            }
+
+            color[start as usize] = GRAY;
+            let mut stack: Vec<(u32, std::vec::IntoIter<u32>)> = vec![(
+                start,
+                self.successors(start, type_id, type_missing, as_of).into_iter(),
+            )];
+
+            while let Some((node, children)) = stack.last_mut() {
+                match children.next() {
+                    Some(next) => {
+                        if self.is_node_deleted(next) {
+                            continue;
+                        }
+                        match color[next as usize] {
+                            WHITE => {
+                                color[next as usize] = GRAY;
+                                let next_children = self.successors(next, type_id, type_missing, as_of);
+                                stack.push((next, next_children.into_iter()));
+                            }
+                            GRAY => {
+                                let mut cycle: Vec<u32> = stack.iter().map(|&(n, _)| n).collect();
+                                if let Some(pos) = cycle.iter().position(|&n| n == next) {
+                                    cycle = cycle[pos..].to_vec();
+                                }
+                                cycle.push(next);
+                                return Err(cycle
+                                    .into_iter()
+                                    .filter_map(|id| self.lookup_str(id).map(|s| s.to_string()))
+                                    .collect());
+                            }
+                            _ => {} // BLACK: fully explored already, skip
+                        }
+                    }
+                    None => {
+                        color[*node as usize] = BLACK;
+                        finished.push(*node);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        finished.reverse();
+        Ok(finished
+            .into_iter()
+            .filter_map(|id| self.lookup_str(id).map(|s| s.to_string()))
+            .collect())
+    }
+
+    fn successors(&self, node: u32, type_id: Option<u8>, type_missing: bool, as_of: Option<i64>) -> Vec<u32> {
+        if type_missing {
+            Vec::new()
+        } else {
+            self.neighbors_any_type(node, type_id, Direction::Outgoing, as_of)
+        }
+    }
+
+    /// Computes the immediate-dominator tree for the slice reachable from `root`,
+    /// following `edge_type`/`direction` edges valid at `as_of`. See
+    /// [`crate::dominator`] for the algorithm.
+    pub fn dominators(
+        &self,
+        root: &str,
+        edge_type: Option<&str>,
+        direction: Direction,
+        as_of: Option<i64>,
+    ) -> crate::dominator::DominatorTree {
+        crate::dominator::compute(self, root, edge_type, direction, as_of)
+    }
+
+    /// Stable 128-bit content hash of the whole graph, as a hex string. Two graphs
+    /// built from the same nodes/edges hash identically regardless of insertion
+    /// order, since `Interner` assigns IDs by first-seen order and bulk ingestion
+    /// order varies. Cheap way to detect whether a re-hydration from DuckDB
+    /// actually changed anything, or to validate a loaded snapshot.
+    pub fn fingerprint(&self) -> String {
+        let mut total: u128 = 0;
+        for (id, node) in self.nodes.iter().enumerate() {
+            if node.deleted {
+                continue;
+            }
+            let Some(name) = self.lookup_str(id as u32) else {
+                continue;
+            };
+
+            let mut edges: Vec<(&str, &str, Option<i64>, Option<i64>)> = node
+                .outgoing
+                .iter()
+                .filter_map(|e| {
+                    let target = self.lookup_str(e.other)?;
+                    let edge_type = self.edge_types.lookup(e.type_id as u32)?;
+                    Some((target, edge_type, e.valid_from, e.valid_to))
+                })
+                .collect();
+            edges.sort_unstable();
+
+            // Commutative, associative mixer: per-node hashes can combine in any
+            // order, so the total doesn't depend on the iteration order above.
+            total = total.wrapping_add(hash_node(name, &edges));
+        }
+        format!("{total:032x}")
+    }
+
+    /// Saves the graph, blocking until an exclusive lock on the snapshot is available.
+    pub fn save_to_file(&self, path: &str) -> Result<(), SnapshotError> {
+        self.save_to_file_with(path, LockMode::Blocking)
+    }
+
+    /// Like `save_to_file`, but returns `SnapshotError::Locked` immediately instead of
+    /// blocking if another process is already saving or loading this snapshot.
+    pub fn try_save_to_file(&self, path: &str) -> Result<(), SnapshotError> {
+        self.save_to_file_with(path, LockMode::NonBlocking)
+    }
+
+    /// Loads a graph, blocking until a shared lock on the snapshot is available.
+    /// Verifies the snapshot's stored fingerprint against the loaded graph's
+    /// recomputed one unless `verify_fingerprint` is `false`, which skips that
+    /// check only: the file must still carry the header that `save_to_file`
+    /// writes, so snapshots saved without a header do not load either way.
+    pub fn load_from_file(path: &str, verify_fingerprint: bool) -> Result<Self, SnapshotError> {
+        Self::load_from_file_with(path, LockMode::Blocking, verify_fingerprint)
+    }
+
+    /// Like `load_from_file`, but returns `SnapshotError::Locked` immediately instead of
+    /// blocking if another process is currently saving this snapshot.
+    pub fn try_load_from_file(path: &str, verify_fingerprint: bool) -> Result<Self, SnapshotError> {
+        Self::load_from_file_with(path, LockMode::NonBlocking, verify_fingerprint)
+    }
+
+    /// Writes to a temp file in the same directory, fsyncs it, then renames it over
+    /// `path` while holding an exclusive lock, so a crash mid-write never leaves a
+    /// truncated snapshot and a concurrent load never observes a half-written one.
+    /// The header carries the graph's `fingerprint()` so a load can validate it
+    /// wasn't corrupted or swapped out from under the caller.
+    fn save_to_file_with(&self, path: &str, lock_mode: LockMode) -> Result<(), SnapshotError> {
+        use std::io::Write;
+
+        let lock_file = open_lock_file(path)?;
+        acquire_lock(&lock_file, lock_mode, true)?;
+
+        let dir = std::path::Path::new(path)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| std::path::Path::new("."));
+        let tmp_path = dir.join(format!(".{}.tmp", tmp_file_suffix()));
+
+        let header = SnapshotHeader { fingerprint: self.fingerprint() };
+        let bytes = bincode::serialize(&(&header, self)).map_err(|e| SnapshotError::Io(e.to_string()))?;
+        let mut tmp_file = std::fs::File::create(&tmp_path).map_err(|e| SnapshotError::Io(e.to_string()))?;
+        tmp_file.write_all(&bytes).map_err(|e| SnapshotError::Io(e.to_string()))?;
+        tmp_file.sync_all().map_err(|e| SnapshotError::Io(e.to_string()))?;
+        drop(tmp_file);
+
+        std::fs::rename(&tmp_path, path).map_err(|e| SnapshotError::Io(e.to_string()))?;
+        Ok(())
+    }
+
+    fn load_from_file_with(path: &str, lock_mode: LockMode, verify_fingerprint: bool) -> Result<Self, SnapshotError> {
+        let lock_file = open_lock_file(path)?;
+        acquire_lock(&lock_file, lock_mode, false)?;
+
+        let bytes = std::fs::read(path).map_err(|e| SnapshotError::Io(e.to_string()))?;
+        let (header, graph): (SnapshotHeader, Self) =
+            bincode::deserialize(&bytes).map_err(|e| SnapshotError::Io(e.to_string()))?;
+
+        if verify_fingerprint {
+            let actual = graph.fingerprint();
+            if actual != header.fingerprint {
+                return Err(SnapshotError::FingerprintMismatch {
+                    expected: header.fingerprint,
+                    actual,
+                });
+            }
+        }
+
+        Ok(graph)
+    }
+}
+
+/// Snapshot file header, written alongside the serialized graph.
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotHeader {
+    fingerprint: String,
+}
+
+/// Fixed, non-cryptographic 128-bit hash of a node's ID and its sorted outgoing
+/// edges, so the result is reproducible across runs and machines (unlike
+/// `HashMap`'s randomized `RandomState`).
+fn hash_node(name: &str, edges: &[(&str, &str, Option<i64>, Option<i64>)]) -> u128 {
+    use siphasher::sip128::{Hasher128, SipHasher13};
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = SipHasher13::new_with_keys(0x71756163_6b677261, 0x7068305f_66703132);
+    name.hash(&mut hasher);
+    edges.hash(&mut hasher);
+    let h = hasher.finish128();
+    (u128::from(h.h1) << 64) | u128::from(h.h2)
+}
+
+/// Whether an advisory lock acquisition should block until available or fail fast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LockMode {
+    Blocking,
+    NonBlocking,
+}
+
+/// A recoverable error from snapshotting: lock contention is reported distinctly
+/// from other I/O failures so a caller can decide whether to wait or skip.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Another process holds a conflicting lock and the caller asked not to block.
+    Locked,
+    Io(String),
+    /// The snapshot's stored fingerprint doesn't match the loaded graph's
+    /// recomputed one, i.e. the file was corrupted or swapped out from under us.
+    FingerprintMismatch { expected: String, actual: String },
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Locked => write!(f, "snapshot is locked by another process"),
+            SnapshotError::Io(msg) => write!(f, "{msg}"),
+            SnapshotError::FingerprintMismatch { expected, actual } => write!(
+                f,
+                "snapshot fingerprint mismatch: expected {expected}, got {actual}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Opens (creating if necessary) the sidecar `<path>.lock` file used to coordinate
+/// concurrent saves/loads of `path`.
+fn open_lock_file(path: &str) -> Result<std::fs::File, SnapshotError> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(format!("{path}.lock"))
+        .map_err(|e| SnapshotError::Io(e.to_string()))
+}
+
+fn acquire_lock(file: &std::fs::File, lock_mode: LockMode, exclusive: bool) -> Result<(), SnapshotError> {
+    use fs4::FileExt;
+
+    let result = match (lock_mode, exclusive) {
+        (LockMode::Blocking, true) => file.lock_exclusive(),
+        (LockMode::Blocking, false) => file.lock_shared(),
+        (LockMode::NonBlocking, true) => file.try_lock_exclusive(),
+        (LockMode::NonBlocking, false) => file.try_lock_shared(),
+    };
+
+    result.map_err(|e| {
+        if lock_mode == LockMode::NonBlocking && e.kind() == std::io::ErrorKind::WouldBlock {
+            SnapshotError::Locked
+        } else {
+            SnapshotError::Io(e.to_string())
+        }
+    })
+}
+
+/// A suffix unique enough to avoid temp-file collisions between concurrent savers
+/// in this process (the lock above already serializes savers across processes).
+fn tmp_file_suffix() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    format!("{:x}-{:x}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+fn is_active_at(valid_from: Option<i64>, valid_to: Option<i64>, as_of: Option<i64>) -> bool {
+    match as_of {
+        None => true,
+        Some(t) => valid_from.is_none_or(|vf| vf <= t) && valid_to.is_none_or(|vt| t < vt),
+    }
+}
+
+fn dedup_edges(edges: &mut Vec<StoredEdge>) {
+    edges.sort_by_key(|e| (e.other, e.type_id, e.valid_from, e.valid_to));
+    edges.dedup_by_key(|e| (e.other, e.type_id, e.valid_from, e.valid_to));
+}
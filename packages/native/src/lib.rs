@@ -2,7 +2,7 @@
 
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
-use quack_core::{matcher::{Matcher, PatternEdge}, GraphIndex, Direction};
+use quack_core::{matcher::{Matcher, PatternEdge}, GraphIndex, Direction, DominatorTree, SnapshotError};
 use arrow::ipc::reader::StreamReader;
 use std::io::Cursor;
 
@@ -154,21 +154,104 @@ impl NativeGraph {
         self.inner.edge_count() as u32
     }
 
+    /// Returns a valid processing order for the `edge_type` slice active at `as_of`
+    /// (e.g. a build/dependency DAG at a point in time). If the slice isn't a DAG,
+    /// fails with an error whose message lists the node IDs forming a cycle.
+    #[napi(js_name = "topologicalOrder")]
+    pub fn topological_order(&self, edge_type: Option<String>, as_of: Option<f64>) -> napi::Result<Vec<String>> {
+        let ts = as_of.map(|t| t as i64);
+        self.inner
+            .topological_order(edge_type.as_deref(), ts)
+            .map_err(|cycle| napi::Error::from_reason(format!("cycle detected: {}", cycle.join(" -> "))))
+    }
+
+    /// Computes the immediate-dominator tree of the slice reachable from `root`.
+    /// Answers "which upstream node, if removed, disconnects this subtree".
+    #[napi]
+    pub fn dominators(&self, root: String, edge_type: Option<String>, direction: Option<String>, as_of: Option<f64>) -> JsDominatorTree {
+        let dir = match direction.as_deref() {
+            Some("in") | Some("IN") => Direction::Incoming,
+            _ => Direction::Outgoing,
+        };
+        let ts = as_of.map(|t| t as i64);
+        JsDominatorTree {
+            inner: self.inner.dominators(&root, edge_type.as_deref(), dir, ts),
+        }
+    }
+
+    /// Saves the graph, blocking until an exclusive lock on the snapshot path is available.
     #[napi]
     pub fn save_snapshot(&self, path: String) -> napi::Result<()> {
-        self.inner.save_to_file(&path).map_err(napi::Error::from_reason)
+        self.inner.save_to_file(&path).map_err(snapshot_err)
+    }
+
+    /// Like `save_snapshot`, but fails immediately instead of blocking if another
+    /// process is already saving or loading this snapshot.
+    #[napi(js_name = "trySaveSnapshot")]
+    pub fn try_save_snapshot(&self, path: String) -> napi::Result<()> {
+        self.inner.try_save_to_file(&path).map_err(snapshot_err)
     }
 
+    /// `verify_fingerprint` defaults to `true`; pass `false` to skip checking the
+    /// stored fingerprint. The snapshot must still carry the header that
+    /// `save_snapshot` writes; files saved without one do not load either way.
     #[napi]
-    pub fn load_snapshot(&mut self, path: String) -> napi::Result<()> {
-        let loaded = GraphIndex::load_from_file(&path).map_err(napi::Error::from_reason)?;
+    pub fn load_snapshot(&mut self, path: String, verify_fingerprint: Option<bool>) -> napi::Result<()> {
+        let loaded = GraphIndex::load_from_file(&path, verify_fingerprint.unwrap_or(true)).map_err(snapshot_err)?;
+        self.inner = loaded;
+        Ok(())
+    }
+
+    /// Like `load_snapshot`, but fails immediately instead of blocking if another
+    /// process is currently saving this snapshot.
+    #[napi(js_name = "tryLoadSnapshot")]
+    pub fn try_load_snapshot(&mut self, path: String, verify_fingerprint: Option<bool>) -> napi::Result<()> {
+        let loaded = GraphIndex::try_load_from_file(&path, verify_fingerprint.unwrap_or(true)).map_err(snapshot_err)?;
         self.inner = loaded;
         Ok(())
     }
+
+    /// Stable 128-bit content hash of the whole graph (hex string), independent of
+    /// insertion order. Cheap way to detect whether a DuckDB re-hydration actually
+    /// changed anything, or to key a query cache.
+    #[napi]
+    pub fn fingerprint(&self) -> String {
+        self.inner.fingerprint()
+    }
 }
 
 impl Default for NativeGraph {
     fn default() -> Self {
         Self::new()
     }
+}
+
+/// Maps a `SnapshotError` to a `napi::Error`. Lock contention is given the
+/// `GenericFailure` status with a recognizable message prefix so a JS caller
+/// can distinguish "someone else is using this snapshot" from other I/O errors
+/// without the native layer needing a richer error ABI.
+fn snapshot_err(e: SnapshotError) -> napi::Error {
+    match &e {
+        SnapshotError::Locked => napi::Error::from_reason(format!("LOCKED: {e}")),
+        SnapshotError::Io(_) | SnapshotError::FingerprintMismatch { .. } => napi::Error::from_reason(e.to_string()),
+    }
+}
+
+/// Thin wrapper so the dominator query result can cross the napi boundary.
+#[napi]
+pub struct JsDominatorTree {
+    inner: DominatorTree,
+}
+
+#[napi]
+impl JsDominatorTree {
+    #[napi(js_name = "immediateDominator")]
+    pub fn immediate_dominator(&self, node: String) -> Option<String> {
+        self.inner.immediate_dominator(&node).map(|s| s.to_string())
+    }
+
+    #[napi]
+    pub fn dominators(&self, node: String) -> Vec<String> {
+        self.inner.dominators(&node)
+    }
 }
\ No newline at end of file